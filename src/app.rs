@@ -0,0 +1,43 @@
+use font::Fonts;
+use metadata::Metadata;
+use settings::Settings;
+use frontlight::Frontlight;
+use battery::Battery;
+use audio::AudioBackend;
+
+// Shared state threaded through every `View`: user settings, the loaded
+// library metadata, fonts, the pluggable hardware backends, and the
+// handful of bits of UI state (inverted/monochrome toggles, the running
+// notification counter) that don't belong to any single view.
+pub struct Context {
+    pub settings: Settings,
+    pub metadata: Metadata,
+    pub fonts: Fonts,
+    pub frontlight: Box<Frontlight>,
+    pub battery: Box<Battery>,
+    pub audio: Box<AudioBackend>,
+    pub inverted: bool,
+    pub monochrome: bool,
+    pub notification_index: u8,
+}
+
+impl Context {
+    pub fn new(settings: Settings,
+               metadata: Metadata,
+               fonts: Fonts,
+               frontlight: Box<Frontlight>,
+               battery: Box<Battery>,
+               audio: Box<AudioBackend>) -> Context {
+        Context {
+            settings,
+            metadata,
+            fonts,
+            frontlight,
+            battery,
+            audio,
+            inverted: false,
+            monochrome: false,
+            notification_index: 0,
+        }
+    }
+}
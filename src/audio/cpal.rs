@@ -0,0 +1,117 @@
+use std::thread;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use cpal::{self, EventLoop, SampleFormat, StreamData, UnknownTypeOutputBuffer};
+use failure::{Error, ResultExt, err_msg};
+use super::AudioBackend;
+
+// Opens the default output device and drives a callback that pulls from a
+// ring buffer fed by the UI thread, so `play_sample`/`push` never block on
+// the audio hardware.
+pub struct CpalAudio {
+    sample_rate: u32,
+    channels: u16,
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+}
+
+impl CpalAudio {
+    pub fn new() -> Result<CpalAudio, Error> {
+        let device = cpal::default_output_device()
+                         .ok_or_else(|| err_msg("No audio output device available."))?;
+        let format = device.default_output_format()
+                            .context("Can't query the default output format.")?;
+        // The output callback below only knows how to fill an
+        // `UnknownTypeOutputBuffer::F32`. On a device whose native format
+        // is I16 or U16 (common on ALSA defaults) that arm would simply
+        // never match and the cue/stream would go out silently with no
+        // error, so refuse to open the stream rather than degrade quietly
+        // at playback time.
+        if format.data_type != SampleFormat::F32 {
+            return Err(err_msg(format!("Unsupported output sample format {:?}: only F32 is supported.",
+                                        format.data_type)));
+        }
+        let sample_rate = format.sample_rate.0;
+        let channels = format.channels;
+        let buffer = Arc::new(Mutex::new(VecDeque::new()));
+
+        let event_loop = EventLoop::new();
+        let stream_id = event_loop.build_output_stream(&device, &format)
+                                   .context("Can't build the output stream.")?;
+        event_loop.play_stream(stream_id);
+
+        let feed = buffer.clone();
+        thread::spawn(move || {
+            event_loop.run(move |_, data| {
+                if let StreamData::Output { buffer: UnknownTypeOutputBuffer::F32(mut out) } = data {
+                    let mut queue = feed.lock().unwrap();
+                    fill_output(&mut out, channels, &mut queue);
+                }
+            });
+        });
+
+        Ok(CpalAudio { sample_rate, channels, buffer })
+    }
+}
+
+// `out` is interleaved per frame (L, R, L, R, ... on a stereo device),
+// but the ring buffer only ever holds one (mono) sample per tick of
+// audio. Pop one sample per frame and duplicate it across every channel
+// instead of popping one sample per slot, or a stereo device plays
+// unrelated consecutive samples as L/R.
+fn fill_output(out: &mut [f32], channels: u16, queue: &mut VecDeque<f32>) {
+    for frame in out.chunks_mut(channels as usize) {
+        let sample = queue.pop_front().unwrap_or(0.0);
+        for slot in frame.iter_mut() {
+            *slot = sample;
+        }
+    }
+}
+
+impl AudioBackend for CpalAudio {
+    fn play_sample(&mut self, samples: &[i16]) {
+        let mut queue = self.buffer.lock().unwrap();
+        queue.extend(samples.iter().map(|&s| f32::from(s) / f32::from(i16::max_value())));
+    }
+
+    fn push(&mut self, samples: &[f32]) {
+        let mut queue = self.buffer.lock().unwrap();
+        queue.extend(samples.iter().cloned());
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stereo_frame_duplicates_one_sample_across_both_channels() {
+        let mut queue: VecDeque<f32> = vec![0.5, -0.25].into();
+        let mut out = [0.0f32; 4];
+        fill_output(&mut out, 2, &mut queue);
+        assert_eq!(out, [0.5, 0.5, -0.25, -0.25]);
+    }
+
+    #[test]
+    fn mono_frame_pops_one_sample_per_slot() {
+        let mut queue: VecDeque<f32> = vec![0.1, 0.2, 0.3].into();
+        let mut out = [0.0f32; 3];
+        fill_output(&mut out, 1, &mut queue);
+        assert_eq!(out, [0.1, 0.2, 0.3]);
+    }
+
+    #[test]
+    fn an_empty_queue_fills_with_silence() {
+        let mut queue: VecDeque<f32> = VecDeque::new();
+        let mut out = [1.0f32; 2];
+        fill_output(&mut out, 2, &mut queue);
+        assert_eq!(out, [0.0, 0.0]);
+    }
+}
@@ -0,0 +1,28 @@
+use failure::Error;
+use super::AudioBackend;
+
+pub struct FakeAudio {
+}
+
+impl FakeAudio {
+    pub fn new() -> Result<FakeAudio, Error> {
+        Ok(FakeAudio {
+        })
+    }
+}
+
+impl AudioBackend for FakeAudio {
+    fn play_sample(&mut self, _samples: &[i16]) {
+    }
+
+    fn push(&mut self, _samples: &[f32]) {
+    }
+
+    fn sample_rate(&self) -> u32 {
+        0
+    }
+
+    fn channels(&self) -> u16 {
+        0
+    }
+}
@@ -0,0 +1,25 @@
+#[cfg(feature = "audio")]
+mod cpal;
+mod fake;
+
+#[cfg(feature = "audio")]
+pub use self::cpal::CpalAudio;
+pub use self::fake::FakeAudio;
+
+// A short, synthesized click used for the page-turn cue: a handful of
+// samples decaying to silence, cheap enough to keep inline instead of
+// shipping a wav file.
+pub const PAGE_TURN_CUE: [i16; 8] = [8000, 6000, 4000, 2000, 1000, 500, 200, 0];
+
+// Pluggable audio output, wired into `Context` the same way `Frontlight`
+// and `Battery` are. `play_sample` fires short one-shot cues (page turns,
+// UI clicks); `push` streams arbitrary PCM, the foundation for a future
+// text-to-speech backend. Device enumeration and stream construction stay
+// behind this trait so `FakeAudio` compiles cleanly without pulling in
+// platform audio libraries when the `audio` feature is off.
+pub trait AudioBackend {
+    fn play_sample(&mut self, samples: &[i16]);
+    fn push(&mut self, samples: &[f32]);
+    fn sample_rate(&self) -> u32;
+    fn channels(&self) -> u16;
+}
@@ -0,0 +1,38 @@
+mod sdl2;
+
+pub use self::sdl2::Sdl2Backend;
+
+use framebuffer::{Framebuffer, UpdateMode};
+use input::DeviceEvent;
+use view::Event;
+use geom::Rectangle;
+use errors::Result;
+
+// Abstracts window creation, per-frame event pumping and presentation so the
+// core loop in `emulator::run` isn't hardwired to a particular windowing
+// library. `Sdl2Backend` is the only implementation today, and `run`
+// constructs it directly; past that one line, `run` only goes through
+// this trait.
+pub trait Backend {
+    type Target: Framebuffer;
+
+    fn framebuffer(&mut self) -> &mut Self::Target;
+    fn dims(&self) -> (u32, u32);
+
+    // Blocks for up to `timeout` milliseconds waiting for the next event.
+    fn poll_event(&mut self, timeout: u32) -> Option<BackendEvent>;
+
+    fn present(&mut self, rect: &Rectangle, mode: UpdateMode) -> Result<u32>;
+    fn save(&self, path: &str) -> Result<()>;
+    fn toggle_inverted(&mut self);
+    fn toggle_monochrome(&mut self);
+}
+
+// What a backend can hand back on a given tick: either something the core
+// loop must act on directly, a synthesized UI event, or a raw device event
+// bound for the gesture pipeline.
+pub enum BackendEvent {
+    Quit,
+    Key(Event),
+    Device(DeviceEvent),
+}
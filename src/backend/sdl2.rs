@@ -0,0 +1,203 @@
+use std::fs::File;
+use sdl2::{self, Sdl, VideoSubsystem};
+use sdl2::event::Event as SdlEvent;
+use sdl2::keyboard::Keycode;
+use sdl2::render::{WindowCanvas, BlendMode};
+use sdl2::pixels::{Color as SdlColor, PixelFormatEnum};
+use sdl2::rect::Point as SdlPoint;
+use std::collections::HashMap;
+use png::HasParameters;
+use framebuffer::{Framebuffer, UpdateMode};
+use input::{DeviceEvent, FingerStatus};
+use view::{Event, EntryId};
+use view::key::KeyKind;
+use geom::{Rectangle, LinearDir};
+use settings::Action;
+use mpd::MpdCommand;
+use errors::*;
+use super::{Backend, BackendEvent};
+
+impl Framebuffer for WindowCanvas {
+    fn set_pixel(&mut self, x: u32, y: u32, color: u8) {
+        self.set_draw_color(SdlColor::RGB(color, color, color));
+        self.draw_point(SdlPoint::new(x as i32, y as i32)).unwrap();
+    }
+
+    fn set_blended_pixel(&mut self, x: u32, y: u32, color: u8, alpha: f32) {
+        self.set_draw_color(SdlColor::RGBA(color, color, color, (alpha * 255.0) as u8));
+        self.draw_point(SdlPoint::new(x as i32, y as i32)).unwrap();
+    }
+
+    fn update(&mut self, _rect: &Rectangle, _mode: UpdateMode) -> Result<u32> {
+        self.present();
+        Ok(1)
+    }
+
+    fn wait(&self, _: u32) -> Result<i32> {
+        Ok(1)
+    }
+
+    fn save(&self, path: &str) -> Result<()> {
+        let (width, height) = self.dims();
+        let file = File::create(path).chain_err(|| "Can't create output file.")?;
+        let mut encoder = png::Encoder::new(file, width, height);
+        encoder.set(png::ColorType::RGB).set(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().chain_err(|| "Can't write header.")?;
+        let data = self.read_pixels(self.viewport(), PixelFormatEnum::RGB24).unwrap_or_default();
+        writer.write_image_data(&data).chain_err(|| "Can't write data to file.")?;
+        Ok(())
+    }
+
+    fn toggle_inverted(&mut self) {}
+
+    fn toggle_monochrome(&mut self) {}
+
+    fn dims(&self) -> (u32, u32) {
+        self.window().size()
+    }
+}
+
+#[inline]
+fn seconds(timestamp: u32) -> f64 {
+    timestamp as f64 / 1000.0
+}
+
+#[inline]
+fn device_event(event: SdlEvent) -> Option<DeviceEvent> {
+    match event {
+        SdlEvent::MouseButtonDown { timestamp, x, y, .. } =>
+            Some(DeviceEvent::Finger { id: 0,
+                                       status: FingerStatus::Down,
+                                       position: pt!(x, y),
+                                       time: seconds(timestamp) }),
+        SdlEvent::MouseButtonUp { timestamp, x, y, .. } =>
+            Some(DeviceEvent::Finger { id: 0,
+                                       status: FingerStatus::Up,
+                                       position: pt!(x, y),
+                                       time: seconds(timestamp) }),
+        SdlEvent::MouseMotion { timestamp, x, y, .. } =>
+            Some(DeviceEvent::Finger { id: 0,
+                                       status: FingerStatus::Motion,
+                                       position: pt!(x, y),
+                                       time: seconds(timestamp) }),
+        _ => None
+    }
+}
+
+// Falls back to the hardcoded shortcuts when a keycode has no entry in the
+// user's key bindings (e.g. modifier keys, which aren't remappable).
+// Escape is included here as a hard quit, not just a fallback for the
+// `back` binding: if a user clears `back` and `quit` to empty strings,
+// this is the only keyboard path left to exit the emulator.
+fn default_key_event(keycode: Keycode) -> Option<Event> {
+    match keycode {
+        Keycode::LShift | Keycode::RShift => Some(Event::Key(KeyKind::Shift)),
+        Keycode::LAlt => Some(Event::Key(KeyKind::Combine)),
+        Keycode::RAlt => Some(Event::Key(KeyKind::Alternate)),
+        Keycode::Return => Some(Event::Key(KeyKind::Return)),
+        Keycode::Backspace => Some(Event::Key(KeyKind::Delete(LinearDir::Backward))),
+        Keycode::Delete => Some(Event::Key(KeyKind::Delete(LinearDir::Forward))),
+        Keycode::Escape => Some(Event::Select(EntryId::Quit)),
+        _ => {
+            let name = keycode.name();
+            if name.len() == 1 {
+                let c = name.chars().next().unwrap().to_lowercase().next().unwrap();
+                Some(Event::Key(KeyKind::Output(c)))
+            } else {
+                None
+            }
+        },
+    }
+}
+
+pub struct Sdl2Backend {
+    context: Sdl,
+    _video: VideoSubsystem,
+    canvas: WindowCanvas,
+    keymap: HashMap<String, Action>,
+}
+
+impl Sdl2Backend {
+    pub fn new(width: u32, height: u32, keymap: HashMap<String, Action>) -> Result<Sdl2Backend> {
+        let context = sdl2::init().map_err(Error::from).chain_err(|| "Can't initialize SDL2.")?;
+        let video = context.video().map_err(Error::from).chain_err(|| "Can't initialize SDL2 video subsystem.")?;
+        let window = video.window("Plato Emulator", width, height)
+                           .position_centered()
+                           .build()
+                           .chain_err(|| "Can't create window.")?;
+        let mut canvas = window.into_canvas().software().build().chain_err(|| "Can't create canvas.")?;
+        canvas.set_blend_mode(BlendMode::Blend);
+        warn_about_unknown_key_names(&keymap);
+        Ok(Sdl2Backend { context, _video: video, canvas, keymap })
+    }
+}
+
+// `KeyBindings::keymap` is backend-agnostic, so it can't tell a real key
+// name from a typo on its own (see `cf0a381`). This is the one place that
+// knows what a valid SDL key name looks like, so it's where that warning
+// belongs now: without it a mistyped binding is just silently unreachable.
+fn warn_about_unknown_key_names(keymap: &HashMap<String, Action>) {
+    for name in keymap.keys() {
+        if Keycode::from_name(name).is_none() {
+            eprintln!("Unknown key name in key bindings: {}.", name);
+        }
+    }
+}
+
+impl Backend for Sdl2Backend {
+    type Target = WindowCanvas;
+
+    fn framebuffer(&mut self) -> &mut WindowCanvas {
+        &mut self.canvas
+    }
+
+    fn dims(&self) -> (u32, u32) {
+        self.canvas.dims()
+    }
+
+    fn poll_event(&mut self, timeout: u32) -> Option<BackendEvent> {
+        let sdl_evt = self.context.event_pump().unwrap().wait_event_timeout(timeout)?;
+        match sdl_evt {
+            SdlEvent::Quit { .. } => Some(BackendEvent::Quit),
+            SdlEvent::KeyDown { keycode: Some(keycode), .. } => {
+                match self.keymap.get(keycode.name().as_str()) {
+                    Some(&Action::PageForward) =>
+                        Some(BackendEvent::Key(Event::Key(KeyKind::Move(LinearDir::Forward)))),
+                    Some(&Action::PageBackward) =>
+                        Some(BackendEvent::Key(Event::Key(KeyKind::Move(LinearDir::Backward)))),
+                    Some(&Action::ToggleInverted) =>
+                        Some(BackendEvent::Key(Event::Select(EntryId::ToggleInverted))),
+                    Some(&Action::Back) =>
+                        Some(BackendEvent::Key(Event::Back)),
+                    Some(&Action::Quit) => Some(BackendEvent::Quit),
+                    Some(&Action::Screenshot) =>
+                        Some(BackendEvent::Key(Event::Select(EntryId::TakeScreenshot))),
+                    Some(&Action::MpdTogglePause) =>
+                        Some(BackendEvent::Key(Event::MpdCommand(MpdCommand::TogglePause))),
+                    Some(&Action::MpdNext) =>
+                        Some(BackendEvent::Key(Event::MpdCommand(MpdCommand::Next))),
+                    Some(&Action::MpdPrevious) =>
+                        Some(BackendEvent::Key(Event::MpdCommand(MpdCommand::Previous))),
+                    None => default_key_event(keycode).map(BackendEvent::Key),
+                }
+            },
+            _ => device_event(sdl_evt).map(BackendEvent::Device),
+        }
+    }
+
+    fn present(&mut self, rect: &Rectangle, mode: UpdateMode) -> Result<u32> {
+        self.canvas.update(rect, mode)
+    }
+
+    fn save(&self, path: &str) -> Result<()> {
+        self.canvas.save(path)
+    }
+
+    fn toggle_inverted(&mut self) {
+        self.canvas.toggle_inverted();
+    }
+
+    fn toggle_monochrome(&mut self) {
+        self.canvas.toggle_monochrome();
+    }
+}
@@ -21,6 +21,11 @@ extern crate fnv;
 extern crate png;
 extern crate isbn;
 extern crate titlecase;
+extern crate gilrs;
+extern crate mpd;
+extern crate failure;
+#[cfg(feature = "audio")]
+extern crate cpal;
 
 #[macro_use]
 mod geom;
@@ -40,6 +45,11 @@ mod settings;
 mod frontlight;
 mod symbolic_path;
 mod app;
+mod gamepad;
+mod backend;
+mod journal;
+mod mpd;
+mod audio;
 
 mod errors {
     error_chain!{
@@ -53,31 +63,22 @@ mod errors {
     }
 }
 
+use std::env;
 use std::thread;
 use std::path::Path;
-use std::fs::File;
 use std::sync::mpsc;
 use std::collections::VecDeque;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use fnv::FnvHashMap;
 use chrono::Local;
-use png::HasParameters;
-use sdl2::event::Event as SdlEvent;
-use sdl2::mouse::MouseButton;
-use sdl2::keyboard::Keycode;
-use sdl2::render::{WindowCanvas, BlendMode};
-use sdl2::pixels::{Color as SdlColor, PixelFormatEnum};
-use sdl2::rect::Point as SdlPoint;
-use framebuffer::{Framebuffer, UpdateMode};
-use input::{DeviceEvent, FingerStatus};
 use view::{View, Event, ViewId, EntryId, render, render_no_wait, handle_event, fill_crack};
 use view::home::Home;
 use view::reader::Reader;
 use view::notification::Notification;
 use view::frontlight::FrontlightWindow;
-use view::key::KeyKind;
 use view::common::{locate, locate_by_id, overlapping_rectangle};
-use geom::{Rectangle, LinearDir};
+use geom::Rectangle;
+use framebuffer::UpdateMode;
 use gesture::gesture_events;
 use device::CURRENT_DEVICE;
 use helpers::{load_json, save_json};
@@ -87,107 +88,110 @@ use frontlight::{Frontlight, FakeFrontlight};
 use battery::{Battery, FakeBattery};
 use font::Fonts;
 use app::Context;
+use backend::{Backend, BackendEvent, Sdl2Backend};
+use audio::{AudioBackend, FakeAudio};
+#[cfg(feature = "audio")]
+use audio::CpalAudio;
 use errors::*;
 
 pub const APP_NAME: &str = "Plato";
 
 const CLOCK_REFRESH_INTERVAL_MS: u64 = 60*1000;
 
+// Builds the audio backend: a real `CpalAudio` when the `audio` feature is
+// enabled and an output device is available, a silent `FakeAudio` otherwise.
+#[cfg(feature = "audio")]
+fn build_audio() -> Box<AudioBackend> {
+    match CpalAudio::new() {
+        Ok(audio) => Box::new(audio) as Box<AudioBackend>,
+        Err(e) => {
+            eprintln!("Can't initialize audio: {}.", e);
+            Box::new(FakeAudio::new().unwrap()) as Box<AudioBackend>
+        },
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+fn build_audio() -> Box<AudioBackend> {
+    Box::new(FakeAudio::new().unwrap()) as Box<AudioBackend>
+}
+
+// Attaches the now-playing widget to `view`'s children when MPD is
+// enabled, sized as a strip at the bottom of the framebuffer rather than
+// the whole screen. Called both for the initial `Home` view and for
+// every `Reader` opened afterwards, since the widget lives on whichever
+// view is currently on top, not in a separate persistent overlay layer.
+fn push_now_playing_widget(view: &mut Box<View>, fb_rect: Rectangle, mpd_enabled: bool) {
+    if mpd_enabled {
+        let widget = mpd::NowPlayingWidget::new(mpd::now_playing_rect(fb_rect));
+        view.children_mut().push(Box::new(widget) as Box<View>);
+    }
+}
+
 pub fn build_context() -> Result<Context> {
     let settings = load_json::<Settings, _>(SETTINGS_PATH)?;
     let path = settings.library_path.join(METADATA_FILENAME);
     let metadata = load_json::<Metadata, _>(path)?;
     let frontlight = Box::new(FakeFrontlight::new()) as Box<Frontlight>;
     let battery = Box::new(FakeBattery::new()) as Box<Battery>;
+    let audio = build_audio();
     let fonts = Fonts::load()?;
-    Ok(Context::new(settings, metadata, fonts, frontlight, battery))
+    Ok(Context::new(settings, metadata, fonts, frontlight, battery, audio))
 }
 
-#[inline]
-fn seconds(timestamp: u32) -> f64 {
-    timestamp as f64 / 1000.0
-}
-
-#[inline]
-pub fn device_event(event: SdlEvent) -> Option<DeviceEvent> {
-    match event {
-        SdlEvent::MouseButtonDown { timestamp, x, y, .. } => 
-            Some(DeviceEvent::Finger { id: 0,
-                                       status: FingerStatus::Down,
-                                       position: pt!(x, y),
-                                       time: seconds(timestamp) }),
-        SdlEvent::MouseButtonUp { timestamp, x, y, .. } =>
-            Some(DeviceEvent::Finger { id: 0,
-                                       status: FingerStatus::Up,
-                                       position: pt!(x, y),
-                                       time: seconds(timestamp) }),
-        SdlEvent::MouseMotion { timestamp, x, y, .. } =>
-            Some(DeviceEvent::Finger { id: 0,
-                                       status: FingerStatus::Motion,
-                                       position: pt!(x, y),
-                                       time: seconds(timestamp) }),
-        _ => None
-    }
+// Parses `--record <path>`, `--replay <path>` and `--time-speed <factor>`
+// out of the process arguments, leaving everything else (there is nothing
+// else yet) untouched.
+struct Args {
+    record_path: Option<String>,
+    replay_path: Option<String>,
+    time_speed: f64,
 }
 
-impl Framebuffer for WindowCanvas {
-    fn set_pixel(&mut self, x: u32, y: u32, color: u8) {
-        self.set_draw_color(SdlColor::RGB(color, color, color));
-        self.draw_point(SdlPoint::new(x as i32, y as i32)).unwrap();
-    }
-
-    fn set_blended_pixel(&mut self, x: u32, y: u32, color: u8, alpha: f32) {
-        self.set_draw_color(SdlColor::RGBA(color, color, color, (alpha * 255.0) as u8));
-        self.draw_point(SdlPoint::new(x as i32, y as i32)).unwrap();
-    }
-
-    fn update(&mut self, _rect: &Rectangle, _mode: UpdateMode) -> Result<u32> {
-        self.present();
-        Ok(1)
-    }
-
-    fn wait(&self, _: u32) -> Result<i32> {
-        Ok(1)
-    }
-
-    fn save(&self, path: &str) -> Result<()> {
-        let (width, height) = self.dims();
-        let file = File::create(path).chain_err(|| "Can't create output file.")?;
-        let mut encoder = png::Encoder::new(file, width, height);
-        encoder.set(png::ColorType::RGB).set(png::BitDepth::Eight);
-        let mut writer = encoder.write_header().chain_err(|| "Can't write header.")?;
-        let data = self.read_pixels(self.viewport(), PixelFormatEnum::RGB24).unwrap_or_default();
-        writer.write_image_data(&data).chain_err(|| "Can't write data to file.")?;
-        Ok(())
-    }
-
-    fn toggle_inverted(&mut self) {}
-
-    fn toggle_monochrome(&mut self) {}
-
-    fn dims(&self) -> (u32, u32) {
-        self.window().size()
+fn parse_args() -> Args {
+    let mut args = Args { record_path: None, replay_path: None, time_speed: 1.0 };
+    let mut iter = env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--record" => args.record_path = iter.next(),
+            "--replay" => args.replay_path = iter.next(),
+            "--time-speed" => {
+                args.time_speed = iter.next().and_then(|v| v.parse().ok()).unwrap_or(1.0);
+            },
+            _ => (),
+        }
     }
+    args
 }
 
 pub fn run() -> Result<()> {
     let mut context = build_context()?;
-    let sdl_context = sdl2::init().unwrap();
-    let video_subsystem = sdl_context.video().unwrap();
     let (width, height) = CURRENT_DEVICE.dims;
-    let window = video_subsystem
-                 .window("Plato Emulator", width, height)
-                 .position_centered()
-                 .build()
-                 .unwrap();
+    let keymap = context.settings.key_bindings.keymap();
+    let mut backend = Sdl2Backend::new(width, height, keymap).chain_err(|| "Can't create backend.")?;
 
-    let mut fb = window.into_canvas().software().build().unwrap();
-    fb.set_blend_mode(BlendMode::Blend);
+    let args = parse_args();
+    let start_time = Instant::now();
+    let mut recorder = match args.record_path {
+        Some(ref path) => Some(journal::Recorder::new(path).chain_err(|| "Can't start recording.")?),
+        None => None,
+    };
 
     let (tx, rx) = mpsc::channel();
     let (ty, ry) = mpsc::channel();
     let touch_screen = gesture_events(ry);
 
+    if let Some(path) = args.replay_path {
+        let ty = ty.clone();
+        let tx = tx.clone();
+        let time_speed = args.time_speed;
+        thread::spawn(move || {
+            if let Err(e) = journal::replay(path, time_speed, ty, tx) {
+                eprintln!("Can't replay journal: {}.", e);
+            }
+        });
+    }
+
     let tx2 = tx.clone();
     thread::spawn(move || {
         while let Ok(evt) = touch_screen.recv() {
@@ -203,11 +207,17 @@ pub fn run() -> Result<()> {
         }
     });
 
-    let fb_rect = fb.rect();
+    gamepad::start(tx.clone());
+    let (mpd_tx, mpd_rx) = mpsc::channel();
+    mpd::start(context.settings.mpd.clone(), tx.clone(), mpd_rx);
+
+    let fb_rect = backend.framebuffer().rect();
 
     let mut history: Vec<Box<View>> = Vec::new();
     let mut view: Box<View> = Box::new(Home::new(fb_rect, &tx, &mut context)?);
 
+    push_now_playing_widget(&mut view, fb_rect, context.settings.mpd.enabled);
+
     let mut updating = FnvHashMap::default();
 
     println!("{} is running on a Kobo {}.", APP_NAME,
@@ -218,72 +228,51 @@ pub fn run() -> Result<()> {
     let mut bus = VecDeque::with_capacity(4);
 
     'outer: loop {
-        if let Some(sdl_evt) = sdl_context.event_pump().unwrap().wait_event_timeout(20) {
-            match sdl_evt {
-                SdlEvent::Quit { .. } => break,
-                SdlEvent::KeyDown { keycode: Some(keycode), .. } => {
-                    match keycode {
-                        Keycode::LShift | Keycode::RShift => {
-                            tx.send(Event::Key(KeyKind::Shift)).unwrap();
-                        },
-                        Keycode::LAlt => {
-                            tx.send(Event::Key(KeyKind::Combine)).unwrap();
-                        },
-                        Keycode::RAlt => {
-                            tx.send(Event::Key(KeyKind::Alternate)).unwrap();
-                        },
-                        Keycode::Return => {
-                            tx.send(Event::Key(KeyKind::Return)).unwrap();
-                        },
-                        Keycode::Left => {
-                            tx.send(Event::Key(KeyKind::Move(LinearDir::Backward))).unwrap();
-                        },
-                        Keycode::Right => {
-                            tx.send(Event::Key(KeyKind::Move(LinearDir::Forward))).unwrap();
-                        },
-                        Keycode::Backspace => {
-                            tx.send(Event::Key(KeyKind::Delete(LinearDir::Backward))).unwrap();
-                        },
-                        Keycode::Delete => {
-                            tx.send(Event::Key(KeyKind::Delete(LinearDir::Forward))).unwrap();
-                        },
-                        Keycode::Escape => break,
-                        _ => {
-                            let name = keycode.name();
-                            if name.len() == 1 {
-                                let c = name.chars().next().unwrap()
-                                            .to_lowercase().next().unwrap();
-                                tx.send(Event::Key(KeyKind::Output(c))).unwrap();
-                            }
-                        },
-
-                    }
-                },
-                _ => {
-                    if let Some(dev_evt) = device_event(sdl_evt) {
-                        ty.send(dev_evt).unwrap();
-                    }
-                },
-            }
+        match backend.poll_event(20) {
+            Some(BackendEvent::Quit) => break,
+            Some(BackendEvent::Key(evt)) => {
+                if let (Some(ref mut rec), &Event::Key(ref kind)) = (&mut recorder, &evt) {
+                    rec.record_key_event(kind, journal::elapsed_seconds(start_time));
+                }
+                tx.send(evt).unwrap();
+            },
+            Some(BackendEvent::Device(dev_evt)) => {
+                if let Some(ref mut rec) = recorder {
+                    rec.record_device_event(&dev_evt);
+                }
+                ty.send(dev_evt).unwrap();
+            },
+            None => (),
         }
 
         while let Ok(evt) = rx.recv_timeout(Duration::from_millis(20)) {
             match evt {
                 Event::Render(mut rect, mode) => {
-                    render(view.as_ref(), &mut rect, &mut fb, &mut context.fonts, &mut updating);
-                    if let Ok(tok) = fb.update(&rect, mode) {
+                    render(view.as_ref(), &mut rect, backend.framebuffer(), &mut context.fonts, &mut updating);
+                    if let Ok(tok) = backend.present(&rect, mode) {
                         updating.insert(tok, rect);
                     }
                 },
+                // Sent by the Reader alongside its own `Event::Render` the
+                // moment it actually turns a page, so the cue doesn't have
+                // to be inferred from `UpdateMode` — a heuristic that
+                // can't tell a page turn apart from other Reader-internal
+                // renders (TOC, search, margin adjustments) that may
+                // reasonably use the same non-Gui modes.
+                Event::PageTurn => {
+                    if context.settings.audio.page_turn_cue {
+                        context.audio.play_sample(&audio::PAGE_TURN_CUE);
+                    }
+                },
                 Event::RenderNoWait(mut rect, mode) => {
-                    render_no_wait(view.as_ref(), &mut rect, &mut fb, &mut context.fonts, &mut updating);
-                    if let Ok(tok) = fb.update(&rect, mode) {
+                    render_no_wait(view.as_ref(), &mut rect, backend.framebuffer(), &mut context.fonts, &mut updating);
+                    if let Ok(tok) = backend.present(&rect, mode) {
                         updating.insert(tok, rect);
                     }
                 },
                 Event::Expose(mut rect) => {
-                    fill_crack(view.as_ref(), &mut rect, &mut fb, &mut context.fonts, &mut updating);
-                    if let Ok(tok) = fb.update(&rect, UpdateMode::Gui) {
+                    fill_crack(view.as_ref(), &mut rect, backend.framebuffer(), &mut context.fonts, &mut updating);
+                    if let Ok(tok) = backend.present(&rect, UpdateMode::Gui) {
                         updating.insert(tok, rect);
                     }
                 },
@@ -291,7 +280,14 @@ pub fn run() -> Result<()> {
                     let info2 = info.clone();
                     if let Some(r) = Reader::new(fb_rect, *info, &tx, &mut context) {
                         history.push(view as Box<View>);
-                        view = Box::new(r) as Box<View>;
+                        let mut r = Box::new(r) as Box<View>;
+                        // The widget is a child of whichever view is on
+                        // top, not a persistent overlay, so it has to be
+                        // re-attached here too, or it (and the "listening
+                        // to music while reading" case it exists for)
+                        // disappears the moment a book is opened.
+                        push_now_playing_widget(&mut r, fb_rect, context.settings.mpd.enabled);
+                        view = r;
                     } else {
                         handle_event(view.as_mut(), &Event::Invalid(info2), &tx, &mut bus, &mut context);
                     }
@@ -325,18 +321,18 @@ pub fn run() -> Result<()> {
                     }
                 },
                 Event::Select(EntryId::ToggleInverted) => {
-                    fb.toggle_inverted();
+                    backend.toggle_inverted();
                     context.inverted = !context.inverted;
                     tx.send(Event::Render(fb_rect, UpdateMode::Gui)).unwrap();
                 },
                 Event::Select(EntryId::ToggleMonochrome) => {
-                    fb.toggle_monochrome();
+                    backend.toggle_monochrome();
                     context.monochrome = !context.monochrome;
                     tx.send(Event::Render(fb_rect, UpdateMode::Gui)).unwrap();
                 },
                 Event::Select(EntryId::TakeScreenshot) => {
                     let name = Local::now().format("screenshot-%Y%m%d_%H%M%S.png");
-                    let msg = match fb.save(&name.to_string()) {
+                    let msg = match backend.save(&name.to_string()) {
                         Err(e) => format!("Couldn't take screenshot: {}).", e),
                         Ok(_) => format!("Saved {}.", name),
                     };
@@ -350,6 +346,16 @@ pub fn run() -> Result<()> {
                 Event::Select(EntryId::Quit) => {
                     break 'outer;
                 },
+                Event::MpdTick(_) => {
+                    handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut context);
+                    if let Some(index) = locate::<mpd::NowPlayingWidget>(view.as_ref()) {
+                        let rect = *view.child(index).rect();
+                        tx.send(Event::Render(rect, UpdateMode::Gui)).unwrap();
+                    }
+                },
+                Event::MpdCommand(cmd) => {
+                    mpd_tx.send(cmd).ok();
+                },
                 _ => {
                     handle_event(view.as_mut(), &evt, &tx, &mut bus, &mut context);
                 },
@@ -0,0 +1,131 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use std::sync::mpsc::Sender;
+use gilrs::{Gilrs, Event as GilrsEvent, EventType, Button, Axis};
+use view::Event;
+use view::key::KeyKind;
+use geom::LinearDir;
+
+// Minimum time between two discrete page turns generated by a held stick.
+const AXIS_REPEAT_MS: u64 = 350;
+const AXIS_THRESHOLD: f32 = 0.6;
+const POLL_TIMEOUT_MS: u64 = 50;
+
+// Spawns a thread that owns the `Gilrs` instance and forwards gamepad input
+// as the same `Event::Key`/`Event::Back` messages produced by the keyboard.
+pub fn start(tx: Sender<Event>) {
+    thread::spawn(move || {
+        let mut gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            Err(e) => {
+                eprintln!("Can't initialize gamepad support: {}.", e);
+                return;
+            },
+        };
+
+        let mut last_axis_repeat = Instant::now() - Duration::from_millis(AXIS_REPEAT_MS);
+
+        loop {
+            while let Some(GilrsEvent { event, id, .. }) = gilrs.next_event() {
+                match event {
+                    EventType::Connected => {
+                        println!("Gamepad {} connected.", id);
+                    },
+                    EventType::Disconnected => {
+                        println!("Gamepad {} disconnected.", id);
+                    },
+                    EventType::ButtonPressed(button, _) => {
+                        if let Some(evt) = button_event(button) {
+                            tx.send(evt).unwrap();
+                        }
+                    },
+                    EventType::AxisChanged(axis, value, _) => {
+                        if let Some(dir) = axis_direction(axis, value) {
+                            let now = Instant::now();
+                            if axis_repeat_elapsed(last_axis_repeat, now) {
+                                last_axis_repeat = now;
+                                tx.send(Event::Key(KeyKind::Move(dir))).unwrap();
+                            }
+                        }
+                    },
+                    _ => (),
+                }
+            }
+            thread::sleep(Duration::from_millis(POLL_TIMEOUT_MS));
+        }
+    });
+}
+
+fn button_event(button: Button) -> Option<Event> {
+    match button {
+        Button::DPadLeft | Button::LeftTrigger | Button::LeftTrigger2 =>
+            Some(Event::Key(KeyKind::Move(LinearDir::Backward))),
+        Button::DPadRight | Button::RightTrigger | Button::RightTrigger2 =>
+            Some(Event::Key(KeyKind::Move(LinearDir::Forward))),
+        Button::South =>
+            Some(Event::Key(KeyKind::Return)),
+        Button::East =>
+            Some(Event::Back),
+        _ => None,
+    }
+}
+
+// An analog stick deflected past the threshold counts as a single page turn,
+// the same way a digital D-pad press does, to avoid flooding the event channel.
+fn axis_direction(axis: Axis, value: f32) -> Option<LinearDir> {
+    match axis {
+        Axis::LeftStickX | Axis::RightStickX => {
+            if value >= AXIS_THRESHOLD {
+                Some(LinearDir::Forward)
+            } else if value <= -AXIS_THRESHOLD {
+                Some(LinearDir::Backward)
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+// Whether enough time has passed since the last axis-triggered page turn
+// to emit another one, so a stick held past the threshold generates
+// discrete turns instead of one per poll.
+fn axis_repeat_elapsed(last_axis_repeat: Instant, now: Instant) -> bool {
+    now.duration_since(last_axis_repeat) >= Duration::from_millis(AXIS_REPEAT_MS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_direction_crosses_positive_threshold() {
+        assert_eq!(axis_direction(Axis::LeftStickX, AXIS_THRESHOLD), Some(LinearDir::Forward));
+        assert_eq!(axis_direction(Axis::RightStickX, 1.0), Some(LinearDir::Forward));
+    }
+
+    #[test]
+    fn axis_direction_crosses_negative_threshold() {
+        assert_eq!(axis_direction(Axis::LeftStickX, -AXIS_THRESHOLD), Some(LinearDir::Backward));
+    }
+
+    #[test]
+    fn axis_direction_ignores_small_deflection() {
+        assert_eq!(axis_direction(Axis::LeftStickX, 0.1), None);
+        assert_eq!(axis_direction(Axis::LeftStickX, -0.1), None);
+    }
+
+    #[test]
+    fn axis_direction_ignores_other_axes() {
+        assert_eq!(axis_direction(Axis::LeftStickY, 1.0), None);
+    }
+
+    #[test]
+    fn axis_repeat_respects_debounce_window() {
+        let last = Instant::now();
+        let too_soon = last + Duration::from_millis(AXIS_REPEAT_MS - 1);
+        let just_enough = last + Duration::from_millis(AXIS_REPEAT_MS);
+        assert!(!axis_repeat_elapsed(last, too_soon));
+        assert!(axis_repeat_elapsed(last, just_enough));
+    }
+}
@@ -0,0 +1,246 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use std::sync::mpsc::Sender;
+use serde_json;
+use input::{DeviceEvent, FingerStatus};
+use view::Event;
+use view::key::KeyKind;
+use geom::LinearDir;
+use errors::*;
+
+// A single recorded tick: an input event plus the elapsed-seconds timestamp
+// it occurred at, so a replay can reproduce the original inter-event delays.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JournalRecord {
+    time: f64,
+    event: JournalEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum JournalEvent {
+    Finger { id: i32, status: JournalFingerStatus, x: i32, y: i32 },
+    Key(JournalKeyKind),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum JournalFingerStatus {
+    Down,
+    Up,
+    Motion,
+}
+
+impl From<FingerStatus> for JournalFingerStatus {
+    fn from(status: FingerStatus) -> JournalFingerStatus {
+        match status {
+            FingerStatus::Down => JournalFingerStatus::Down,
+            FingerStatus::Up => JournalFingerStatus::Up,
+            FingerStatus::Motion => JournalFingerStatus::Motion,
+        }
+    }
+}
+
+impl From<JournalFingerStatus> for FingerStatus {
+    fn from(status: JournalFingerStatus) -> FingerStatus {
+        match status {
+            JournalFingerStatus::Down => FingerStatus::Down,
+            JournalFingerStatus::Up => FingerStatus::Up,
+            JournalFingerStatus::Motion => FingerStatus::Motion,
+        }
+    }
+}
+
+// Mirrors the handful of `KeyKind` variants the emulator synthesizes from
+// keyboard/gamepad input, so they can round-trip through JSON without
+// requiring `view::key::KeyKind` itself to derive `Serialize`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum JournalKeyKind {
+    Shift,
+    Combine,
+    Alternate,
+    Return,
+    MoveForward,
+    MoveBackward,
+    DeleteForward,
+    DeleteBackward,
+    Output(char),
+}
+
+impl JournalKeyKind {
+    fn from_key_kind(kind: &KeyKind) -> Option<JournalKeyKind> {
+        match *kind {
+            KeyKind::Shift => Some(JournalKeyKind::Shift),
+            KeyKind::Combine => Some(JournalKeyKind::Combine),
+            KeyKind::Alternate => Some(JournalKeyKind::Alternate),
+            KeyKind::Return => Some(JournalKeyKind::Return),
+            KeyKind::Move(LinearDir::Forward) => Some(JournalKeyKind::MoveForward),
+            KeyKind::Move(LinearDir::Backward) => Some(JournalKeyKind::MoveBackward),
+            KeyKind::Delete(LinearDir::Forward) => Some(JournalKeyKind::DeleteForward),
+            KeyKind::Delete(LinearDir::Backward) => Some(JournalKeyKind::DeleteBackward),
+            KeyKind::Output(c) => Some(JournalKeyKind::Output(c)),
+        }
+    }
+
+    fn into_key_kind(self) -> KeyKind {
+        match self {
+            JournalKeyKind::Shift => KeyKind::Shift,
+            JournalKeyKind::Combine => KeyKind::Combine,
+            JournalKeyKind::Alternate => KeyKind::Alternate,
+            JournalKeyKind::Return => KeyKind::Return,
+            JournalKeyKind::MoveForward => KeyKind::Move(LinearDir::Forward),
+            JournalKeyKind::MoveBackward => KeyKind::Move(LinearDir::Backward),
+            JournalKeyKind::DeleteForward => KeyKind::Delete(LinearDir::Forward),
+            JournalKeyKind::DeleteBackward => KeyKind::Delete(LinearDir::Backward),
+            JournalKeyKind::Output(c) => KeyKind::Output(c),
+        }
+    }
+}
+
+// Seconds elapsed since `start`, used to timestamp key events, which don't
+// carry a timestamp of their own. Device events are recorded with the
+// `time` their `DeviceEvent` carries, so the two kinds of record are on
+// different clocks.
+pub fn elapsed_seconds(start: Instant) -> f64 {
+    let elapsed = start.elapsed();
+    elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) * 1e-9
+}
+
+// Writes the `DeviceEvent::Finger`s and `Event::Key`s that the backend
+// hands back to `run`, before they go into the `ty`/`tx` channels, to a
+// file as newline-delimited JSON. Events sent straight onto `tx` by other
+// threads (the gamepad, a replay) are not recorded.
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Recorder> {
+        let file = File::create(path).chain_err(|| "Can't create journal file.")?;
+        Ok(Recorder { writer: BufWriter::new(file) })
+    }
+
+    pub fn record_device_event(&mut self, evt: &DeviceEvent) {
+        if let DeviceEvent::Finger { id, status, position, time } = *evt {
+            self.write(time, JournalEvent::Finger { id, status: status.into(), x: position.x, y: position.y });
+        }
+    }
+
+    pub fn record_key_event(&mut self, kind: &KeyKind, time: f64) {
+        if let Some(jk) = JournalKeyKind::from_key_kind(kind) {
+            self.write(time, JournalEvent::Key(jk));
+        }
+    }
+
+    // Flushed after every record: this is meant to capture crash repros,
+    // exactly the case where the process won't reach the clean `Drop` at
+    // the end of `run()` that would otherwise flush the `BufWriter`.
+    fn write(&mut self, time: f64, event: JournalEvent) {
+        let record = JournalRecord { time, event };
+        match serde_json::to_string(&record) {
+            Ok(line) => {
+                let _ = writeln!(self.writer, "{}", line);
+                let _ = self.writer.flush();
+            },
+            Err(e) => eprintln!("Can't serialize journal record: {}.", e),
+        }
+    }
+}
+
+// Reads a previously recorded journal and re-injects its events into `ty`
+// and `tx`, honoring the original inter-event delays scaled by
+// `time_speed` (1.0 plays back in real time, higher values fast-forward).
+pub fn replay<P: AsRef<Path>>(path: P, time_speed: f64, ty: Sender<DeviceEvent>, tx: Sender<Event>) -> Result<()> {
+    let file = File::open(path).chain_err(|| "Can't open journal file.")?;
+    let reader = BufReader::new(file);
+    let mut last_time = None;
+
+    for line in reader.lines() {
+        let line = line.chain_err(|| "Can't read journal file.")?;
+        let record: JournalRecord = serde_json::from_str(&line).chain_err(|| "Can't parse journal record.")?;
+
+        if let Some(previous) = last_time {
+            let delay = ((record.time - previous) / time_speed).max(0.0);
+            thread::sleep(Duration::from_millis((delay * 1000.0) as u64));
+        }
+        last_time = Some(record.time);
+
+        match record.event {
+            JournalEvent::Finger { id, status, x, y } => {
+                ty.send(DeviceEvent::Finger { id, status: status.into(), position: pt!(x, y), time: record.time }).unwrap();
+            },
+            JournalEvent::Key(jk) => {
+                tx.send(Event::Key(jk.into_key_kind())).unwrap();
+            },
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn same_dir(a: LinearDir, b: LinearDir) -> bool {
+        match (a, b) {
+            (LinearDir::Forward, LinearDir::Forward) => true,
+            (LinearDir::Backward, LinearDir::Backward) => true,
+            _ => false,
+        }
+    }
+
+    fn all_kinds() -> Vec<KeyKind> {
+        vec![KeyKind::Shift,
+             KeyKind::Combine,
+             KeyKind::Alternate,
+             KeyKind::Return,
+             KeyKind::Move(LinearDir::Forward),
+             KeyKind::Move(LinearDir::Backward),
+             KeyKind::Delete(LinearDir::Forward),
+             KeyKind::Delete(LinearDir::Backward),
+             KeyKind::Output('a')]
+    }
+
+    #[test]
+    fn key_kind_round_trips_through_journal_key_kind() {
+        for kind in all_kinds() {
+            let jk = JournalKeyKind::from_key_kind(&kind).unwrap();
+            let restored = jk.into_key_kind();
+            let matches = match (kind, restored) {
+                (KeyKind::Shift, KeyKind::Shift) => true,
+                (KeyKind::Combine, KeyKind::Combine) => true,
+                (KeyKind::Alternate, KeyKind::Alternate) => true,
+                (KeyKind::Return, KeyKind::Return) => true,
+                (KeyKind::Move(a), KeyKind::Move(b)) => same_dir(a, b),
+                (KeyKind::Delete(a), KeyKind::Delete(b)) => same_dir(a, b),
+                (KeyKind::Output(a), KeyKind::Output(b)) => a == b,
+                _ => false,
+            };
+            assert!(matches, "JournalKeyKind round-trip changed the key kind");
+        }
+    }
+
+    #[test]
+    fn finger_status_round_trips() {
+        for status in &[FingerStatus::Down, FingerStatus::Up, FingerStatus::Motion] {
+            let jfs: JournalFingerStatus = (*status).into();
+            let back: FingerStatus = jfs.into();
+            let matches = match (status, &back) {
+                (FingerStatus::Down, FingerStatus::Down) => true,
+                (FingerStatus::Up, FingerStatus::Up) => true,
+                (FingerStatus::Motion, FingerStatus::Motion) => true,
+                _ => false,
+            };
+            assert!(matches, "FingerStatus round-trip changed the status");
+        }
+    }
+
+    #[test]
+    fn elapsed_seconds_increases_with_time() {
+        let start = Instant::now();
+        thread::sleep(Duration::from_millis(5));
+        assert!(elapsed_seconds(start) > 0.0);
+    }
+}
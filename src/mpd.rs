@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
+use std::sync::mpsc::{Sender, Receiver};
+use mpd::{Client, Song, Status};
+use geom::Rectangle;
+use framebuffer::Framebuffer;
+use font::Fonts;
+use view::{View, Event};
+use settings::MpdSettings;
+use app::Context;
+
+const MPD_POLL_INTERVAL_MS: u64 = 333;
+
+// The compact summary the widget renders: enough to show "title — artist"
+// and a `mm:ss / mm:ss` timer without holding on to the full `mpd::Song`.
+#[derive(Debug, Clone)]
+pub struct NowPlaying {
+    pub title: String,
+    pub artist: String,
+    pub elapsed: u32,
+    pub total: u32,
+}
+
+fn now_playing(status: &Status, song: &Song) -> NowPlaying {
+    let (elapsed, total) = status.time.map(|(e, t)| (e.num_seconds() as u32, t.num_seconds() as u32))
+                                       .unwrap_or((0, 0));
+    NowPlaying {
+        title: song.title.clone().unwrap_or_else(|| song.file.clone()),
+        artist: song.tags.get("Artist").cloned().unwrap_or_default(),
+        elapsed,
+        total,
+    }
+}
+
+// Turns a seconds count into a `mm:ss` string.
+pub fn format_seconds(seconds: u32) -> String {
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+// The transport commands that can be issued back into the MPD session.
+#[derive(Debug, Clone, Copy)]
+pub enum MpdCommand {
+    TogglePause,
+    Next,
+    Previous,
+}
+
+// Connects to the configured MPD server and polls its status on a fixed
+// interval, sending `Event::MpdTick(Some(..))` while MPD has a current song
+// and `Event::MpdTick(None)` once it has none or the connection drops.
+// A server that's never reachable in the first place is not an error:
+// this thread just keeps retrying quietly and the widget never appears.
+// `commands` carries transport requests the other way (the key-bound
+// `Event::MpdCommand`s forwarded by `run`), drained once per poll so
+// they're applied before the next tick.
+pub fn start(settings: MpdSettings, tx: Sender<Event>, commands: Receiver<MpdCommand>) {
+    if !settings.enabled {
+        return;
+    }
+
+    thread::spawn(move || {
+        loop {
+            match Client::connect((settings.host.as_str(), settings.port)) {
+                Ok(mut client) => {
+                    loop {
+                        while let Ok(cmd) = commands.try_recv() {
+                            let result = match cmd {
+                                MpdCommand::TogglePause => client.toggle_pause(),
+                                MpdCommand::Next => client.next(),
+                                MpdCommand::Previous => client.prev(),
+                            };
+                            if let Err(e) = result {
+                                eprintln!("Can't send MPD command: {}.", e);
+                            }
+                        }
+                        let tick = match (client.status(), client.currentsong()) {
+                            (Ok(status), Ok(Some(song))) => Some(now_playing(&status, &song)),
+                            (Ok(_), Ok(None)) => None,
+                            _ => {
+                                let _ = tx.send(Event::MpdTick(None));
+                                break;
+                            },
+                        };
+                        if tx.send(Event::MpdTick(tick)).is_err() {
+                            return;
+                        }
+                        thread::sleep(Duration::from_millis(MPD_POLL_INTERVAL_MS));
+                    }
+                },
+                Err(_) => (),
+            }
+            // Reached both when the initial connect fails and when the
+            // inner loop breaks out on a failed status/currentsong call,
+            // so a server that starts failing mid-session doesn't spin
+            // this thread in a tight reconnect loop.
+            thread::sleep(Duration::from_millis(MPD_POLL_INTERVAL_MS));
+        }
+    });
+}
+
+// Fraction of the screen height the widget occupies. Kept small so an
+// MPD tick only costs a partial refresh of a thin strip instead of a
+// full-page one, and so the widget's rect doesn't cover enough of the
+// screen to swallow taps meant for the view underneath.
+const NOW_PLAYING_HEIGHT_DIV: i32 = 18;
+
+// The widget's bounding rect: a strip along the bottom of the
+// framebuffer, not the whole screen.
+pub fn now_playing_rect(fb_rect: Rectangle) -> Rectangle {
+    let height = fb_rect.height() as i32 / NOW_PLAYING_HEIGHT_DIV;
+    Rectangle {
+        min: pt!(fb_rect.min.x, fb_rect.max.y - height),
+        max: pt!(fb_rect.max.x, fb_rect.max.y),
+    }
+}
+
+// A compact overlay showing the current track and its elapsed/total time,
+// analogous to `view::notification::Notification`. It draws no transport
+// controls; those are key bindings (see `handle_event` below).
+pub struct NowPlayingWidget {
+    rect: Rectangle,
+    children: Vec<Box<View>>,
+    track: Option<NowPlaying>,
+}
+
+impl NowPlayingWidget {
+    pub fn new(rect: Rectangle) -> NowPlayingWidget {
+        NowPlayingWidget { rect, children: Vec::new(), track: None }
+    }
+
+    pub fn label(&self) -> String {
+        match self.track {
+            Some(ref np) => format!("{} — {}    {} / {}", np.title, np.artist,
+                                     format_seconds(np.elapsed), format_seconds(np.total)),
+            None => String::new(),
+        }
+    }
+}
+
+impl View for NowPlayingWidget {
+    // Transport shortcuts (play/pause, next, previous) aren't handled here:
+    // they're bound through `KeyBindings`/`Action` like every other
+    // shortcut (see `Sdl2Backend::poll_event`) and reach the MPD thread
+    // straight off the backend as `Event::MpdCommand`, so the widget only
+    // has to track what to render.
+    fn handle_event(&mut self, evt: &Event, _hub: &Sender<Event>, _bus: &mut VecDeque<Event>, _context: &mut Context) -> bool {
+        match *evt {
+            Event::MpdTick(ref track) => {
+                self.track = track.clone();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn render(&self, fb: &mut Framebuffer, fonts: &mut Fonts) {
+        let label = self.label();
+        if label.is_empty() {
+            return;
+        }
+        let font = fonts.sans_serif.normal.as_mut();
+        let padding = self.rect.height() as i32 / 4;
+        let pt = pt!(self.rect.min.x + padding, self.rect.max.y - padding);
+        font.render(fb, &label, pt);
+    }
+
+    fn rect(&self) -> &Rectangle {
+        &self.rect
+    }
+
+    fn rect_mut(&mut self) -> &mut Rectangle {
+        &mut self.rect
+    }
+
+    fn children(&self) -> &Vec<Box<View>> {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut Vec<Box<View>> {
+        &mut self.children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_seconds_pads_to_two_digits() {
+        assert_eq!(format_seconds(0), "00:00");
+        assert_eq!(format_seconds(65), "01:05");
+        assert_eq!(format_seconds(3599), "59:59");
+    }
+
+    #[test]
+    fn now_playing_rect_is_a_bottom_strip_not_the_full_screen() {
+        let fb_rect = Rectangle { min: pt!(0, 0), max: pt!(600, 800) };
+        let rect = now_playing_rect(fb_rect);
+        assert_eq!(rect.max.x, fb_rect.max.x);
+        assert_eq!(rect.max.y, fb_rect.max.y);
+        assert_eq!(rect.min.x, fb_rect.min.x);
+        assert!(rect.min.y > fb_rect.min.y);
+        assert!(rect.height() < fb_rect.height());
+    }
+}
@@ -0,0 +1,197 @@
+use std::path::PathBuf;
+use std::collections::HashMap;
+
+pub const SETTINGS_PATH: &str = "Settings.json";
+
+// The logical actions that a key (or, eventually, a gamepad button) can be
+// bound to. Kept distinct from `view::key::KeyKind` so the binding table
+// doesn't need to know about every key the reader understands, only the
+// handful of shortcuts users actually want to remap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    PageForward,
+    PageBackward,
+    ToggleInverted,
+    Back,
+    Quit,
+    Screenshot,
+    MpdTogglePause,
+    MpdNext,
+    MpdPrevious,
+}
+
+// A table mapping action names to key names, e.g. `{"page_forward": "Right"}`.
+// Loaded as part of `Settings` and resolved to a name-keyed lookup once at
+// startup instead of being consulted field by field on every event. Key
+// names are kept as plain strings rather than a concrete `sdl2::Keycode`
+// so this stays reusable by any future `Backend` impl: each backend maps
+// its own native key representation to a name (e.g. `Keycode::name()`)
+// before consulting this table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub page_forward: String,
+    pub page_backward: String,
+    pub toggle_inverted: String,
+    pub back: String,
+    pub quit: String,
+    pub screenshot: String,
+    pub mpd_toggle_pause: String,
+    pub mpd_next: String,
+    pub mpd_previous: String,
+}
+
+impl Default for KeyBindings {
+    fn default() -> KeyBindings {
+        KeyBindings {
+            page_forward: "Right".to_string(),
+            page_backward: "Left".to_string(),
+            toggle_inverted: "I".to_string(),
+            // Not "Escape": the keymap lookup is consulted before
+            // `default_key_event`'s hardcoded quit fallback, so binding
+            // `back` to Escape by default would silently turn the
+            // out-of-the-box "Escape quits" behavior into "Escape goes
+            // back" (a no-op on Home, since there's no history to pop).
+            back: "B".to_string(),
+            quit: "Q".to_string(),
+            screenshot: "S".to_string(),
+            // Routed through the same table as every other shortcut (see
+            // `Sdl2Backend::poll_event`) instead of being matched as plain
+            // `KeyKind::Output` characters inside `NowPlayingWidget`, so a
+            // collision with another default (or a user's own remap) is
+            // at least a visible "last pair in `keymap()` wins", not a
+            // key that silently never reaches the widget at all.
+            mpd_toggle_pause: "P".to_string(),
+            mpd_next: "N".to_string(),
+            mpd_previous: "V".to_string(),
+        }
+    }
+}
+
+impl KeyBindings {
+    // Builds a key name -> `Action` lookup. Backend-agnostic on purpose:
+    // a backend resolves its own native key to a name string (e.g.
+    // `Keycode::name()` for SDL2) and looks it up here, so the binding
+    // table itself never depends on a particular windowing library.
+    pub fn keymap(&self) -> HashMap<String, Action> {
+        let mut map = HashMap::new();
+        let pairs = [
+            (&self.page_forward, Action::PageForward),
+            (&self.page_backward, Action::PageBackward),
+            (&self.toggle_inverted, Action::ToggleInverted),
+            (&self.back, Action::Back),
+            (&self.quit, Action::Quit),
+            (&self.screenshot, Action::Screenshot),
+            (&self.mpd_toggle_pause, Action::MpdTogglePause),
+            (&self.mpd_next, Action::MpdNext),
+            (&self.mpd_previous, Action::MpdPrevious),
+        ];
+        for &(name, action) in &pairs {
+            if !name.is_empty() {
+                map.insert(name.clone(), action);
+            }
+        }
+        map
+    }
+}
+
+// Host, port and enable flag for the optional now-playing widget. Left
+// disabled by default so readers who don't run MPD never pay for the
+// connection attempt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MpdSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for MpdSettings {
+    fn default() -> MpdSettings {
+        MpdSettings {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: 6600,
+        }
+    }
+}
+
+// Whether to play short audio cues for UI events. Off by default: most
+// Kobo devices are used silently, and the emulator's `FakeAudio` backend
+// makes this a no-op unless the `audio` feature is built in anyway.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub page_turn_cue: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> AudioSettings {
+        AudioSettings {
+            page_turn_cue: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub library_path: PathBuf,
+    pub frontlight: bool,
+    pub key_bindings: KeyBindings,
+    pub mpd: MpdSettings,
+    pub audio: AudioSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            library_path: PathBuf::from("."),
+            frontlight: true,
+            key_bindings: KeyBindings::default(),
+            mpd: MpdSettings::default(),
+            audio: AudioSettings::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_keymap_resolves_every_binding() {
+        let map = KeyBindings::default().keymap();
+        assert_eq!(map.get("Right"), Some(&Action::PageForward));
+        assert_eq!(map.get("Left"), Some(&Action::PageBackward));
+        assert_eq!(map.get("I"), Some(&Action::ToggleInverted));
+        assert_eq!(map.get("B"), Some(&Action::Back));
+        assert_eq!(map.get("Q"), Some(&Action::Quit));
+        assert_eq!(map.get("S"), Some(&Action::Screenshot));
+        assert_eq!(map.get("P"), Some(&Action::MpdTogglePause));
+        assert_eq!(map.get("N"), Some(&Action::MpdNext));
+        assert_eq!(map.get("V"), Some(&Action::MpdPrevious));
+    }
+
+    #[test]
+    fn default_bindings_dont_collide_with_each_other() {
+        let map = KeyBindings::default().keymap();
+        assert_eq!(map.len(), 9);
+    }
+
+    #[test]
+    fn default_back_binding_leaves_escape_free_to_quit() {
+        // Escape must stay unbound so `default_key_event`'s hardcoded
+        // `Keycode::Escape => Some(Event::Select(EntryId::Quit))` fallback
+        // still fires.
+        assert_ne!(KeyBindings::default().back, "Escape");
+    }
+
+    #[test]
+    fn empty_binding_is_omitted_from_the_keymap() {
+        let mut bindings = KeyBindings::default();
+        bindings.screenshot = String::new();
+        assert_eq!(bindings.keymap().get(""), None);
+        assert_eq!(bindings.keymap().len(), 8);
+    }
+}